@@ -0,0 +1,29 @@
+//! Benchmarks `ore_cli::mine::hash_with_memory`, the crate's drillx hash over a reused
+//! `SolverMemory`; `find_hash_par` runs the same hash on every core through
+//! `drillx::hash_with_memory`.
+//!
+//! To measure the gain from the `jemalloc` feature, run this twice and compare the
+//! reported throughput:
+//!
+//!     cargo bench --bench hashrate
+//!     cargo bench --bench hashrate --features jemalloc
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use drillx::equix;
+use ore_cli::mine::hash_with_memory;
+
+fn hash_with_memory_benchmark(c: &mut Criterion) {
+    let challenge = [0u8; 32];
+    let mut memory = equix::SolverMemory::new();
+    let mut nonce: u64 = 0;
+
+    c.bench_function("hash_with_memory", |b| {
+        b.iter(|| {
+            nonce = nonce.wrapping_add(1);
+            let _ = hash_with_memory(&mut memory, &challenge, &nonce.to_le_bytes());
+        })
+    });
+}
+
+criterion_group!(benches, hash_with_memory_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,140 @@
+use std::fmt;
+
+/// Maximum difficulty drillx can report (the number of bits in a 32-byte hash).
+const MAX_DIFFICULTY: u32 = 256;
+
+/// A validated mining difficulty.
+///
+/// Difficulty is threaded through `find_hash_par`, the priority-fee ladder, and
+/// `min_difficulty`; as a bare `u32` it would be easy for a stray subtraction to
+/// underflow or for a threshold comparison to silently compare the wrong values. This
+/// newtype centralizes construction and the saturating arithmetic needed for "remaining
+/// difficulty to target" so there's one place to get it right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Difficulty(u32);
+
+/// Error returned when a raw value can't be represented as a `Difficulty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyError(u32);
+
+impl fmt::Display for DifficultyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "difficulty {} exceeds the maximum of {}",
+            self.0, MAX_DIFFICULTY
+        )
+    }
+}
+
+impl std::error::Error for DifficultyError {}
+
+impl Difficulty {
+    pub const MIN: Difficulty = Difficulty(0);
+
+    /// Builds a `Difficulty`, validating that `value` is within drillx's representable range.
+    pub fn new(value: u32) -> Result<Self, DifficultyError> {
+        if value > MAX_DIFFICULTY {
+            return Err(DifficultyError(value));
+        }
+        Ok(Difficulty(value))
+    }
+
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Difficulty still needed to reach `target`, without underflowing if we're already there.
+    pub fn remaining(self, target: Difficulty) -> Difficulty {
+        Difficulty(target.0.saturating_sub(self.0))
+    }
+
+    pub fn saturating_add(self, rhs: u32) -> Self {
+        Difficulty(self.0.saturating_add(rhs).min(MAX_DIFFICULTY))
+    }
+
+    pub fn saturating_sub(self, rhs: Difficulty) -> Self {
+        Difficulty(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Infallible: every `u32` drillx actually produces (a leading-zero count) fits.
+impl From<u32> for Difficulty {
+    fn from(value: u32) -> Self {
+        debug_assert!(value <= MAX_DIFFICULTY, "difficulty out of range: {value}");
+        Difficulty(value)
+    }
+}
+
+impl From<Difficulty> for u32 {
+    fn from(value: Difficulty) -> Self {
+        value.0
+    }
+}
+
+/// Fallible at config/RPC boundaries, where the raw value isn't guaranteed in range.
+impl TryFrom<u64> for Difficulty {
+    type Error = DifficultyError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        let value: u32 = value.try_into().map_err(|_| DifficultyError(u32::MAX))?;
+        Difficulty::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_accepts_max_difficulty_and_rejects_above() {
+        assert!(Difficulty::new(MAX_DIFFICULTY).is_ok());
+        assert_eq!(
+            Difficulty::new(MAX_DIFFICULTY + 1),
+            Err(DifficultyError(MAX_DIFFICULTY + 1))
+        );
+    }
+
+    #[test]
+    fn remaining_does_not_underflow_past_target() {
+        let below = Difficulty::new(10).unwrap();
+        let target = Difficulty::new(18).unwrap();
+        assert_eq!(below.remaining(target), Difficulty::new(8).unwrap());
+
+        // Already at or past target: no negative remainder, saturates to zero.
+        assert_eq!(target.remaining(target), Difficulty::MIN);
+        let above = Difficulty::new(24).unwrap();
+        assert_eq!(above.remaining(target), Difficulty::MIN);
+    }
+
+    #[test]
+    fn saturating_add_caps_at_max_difficulty() {
+        let near_max = Difficulty::new(MAX_DIFFICULTY - 1).unwrap();
+        assert_eq!(near_max.saturating_add(1), Difficulty::new(MAX_DIFFICULTY).unwrap());
+        assert_eq!(near_max.saturating_add(10), Difficulty::new(MAX_DIFFICULTY).unwrap());
+    }
+
+    #[test]
+    fn saturating_sub_floors_at_zero() {
+        let small = Difficulty::new(2).unwrap();
+        let large = Difficulty::new(5).unwrap();
+        assert_eq!(small.saturating_sub(large), Difficulty::MIN);
+        assert_eq!(large.saturating_sub(small), Difficulty::new(3).unwrap());
+    }
+
+    #[test]
+    fn try_from_u64_boundary_and_error() {
+        assert_eq!(
+            Difficulty::try_from(MAX_DIFFICULTY as u64).unwrap(),
+            Difficulty::new(MAX_DIFFICULTY).unwrap()
+        );
+        assert!(Difficulty::try_from(MAX_DIFFICULTY as u64 + 1).is_err());
+        assert!(Difficulty::try_from(u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+//! Crate root shared by the `ore` binary and the benches.
+//!
+//! Only the modules this backlog chunk actually touches are declared here
+//! (`difficulty`, `mine`); the rest of the crate (`args`, `constants`,
+//! `send_and_confirm`, `utils`, the `Miner` type, and CLI wiring) lives outside this
+//! checkout.
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+pub mod difficulty;
+pub mod mine;
@@ -0,0 +1,8 @@
+// Reference the library so it is linked, and with it the allocator it declares.
+use ore_cli as _;
+
+fn main() {
+    // CLI entry point (arg parsing, subcommand dispatch) lives outside this checkout.
+    // The `#[global_allocator]` behind the `jemalloc` feature is declared in `lib.rs`
+    // so it applies to this binary and to the benches alike.
+}
@@ -1,4 +1,11 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
 
 use colored::*;
 use drillx::{
@@ -18,11 +25,15 @@ use solana_sdk::signer::Signer;
 use crate::{
     args::MineArgs,
     constants,
+    difficulty::Difficulty,
     send_and_confirm::ComputeBudget,
     utils::{amount_u64_to_string, get_clock, get_config, get_proof_with_authority, proof_pubkey},
     Miner,
 };
 
+/// Number of aggregate H/s samples kept for the moving-average hashrate display.
+const HASHRATE_WINDOW: usize = 5;
+
 impl Miner {
     pub async fn mine(&self, args: MineArgs) {
         // Register, if needed.
@@ -32,6 +43,17 @@ impl Miner {
         // Check num threads
         self.check_num_cores(args.cores);
 
+        // Validate the operator-supplied target once per invocation, so a mistyped CLI
+        // flag (ordinary user error) prints a clean message and exits instead of panicking
+        // the whole miner on the first pass through the loop below.
+        let expected_min_difficulty = match Difficulty::new(args.expected_min_difficulty) {
+            Ok(difficulty) => difficulty,
+            Err(err) => {
+                println!("{} {}", "ERROR".bold().red(), err);
+                return;
+            }
+        };
+
         // Start mining loop
         loop {
             // Fetch proof
@@ -48,11 +70,19 @@ impl Miner {
 
             // Run drillx
             let config = get_config(&self.rpc_client).await;
+            let protocol_min_difficulty =
+                Difficulty::try_from(config.min_difficulty as u64).unwrap_or(Difficulty::MIN);
+            // Never let a low/stale operator target undercut the on-chain minimum: the
+            // program will reject a submission below it regardless, so terminating on
+            // anything less would just burn a tx fee every cycle.
+            let target_difficulty = expected_min_difficulty.max(protocol_min_difficulty);
             let (solution, best_difficulty) = Self::find_hash_par(
                 proof,
                 cutoff_time,
                 args.cores,
-                config.min_difficulty as u32,
+                target_difficulty,
+                args.risk_time,
+                args.nonce_checkpoint_step,
             )
             .await;
 
@@ -63,25 +93,28 @@ impl Miner {
                 compute_budget += 100_000;
                 ixs.push(ore_api::instruction::reset(signer.pubkey()));
             }
+            let bus = find_bus();
             ixs.push(ore_api::instruction::mine(
                 signer.pubkey(),
                 signer.pubkey(),
-                find_bus(),
+                bus,
                 solution,
             ));
 
-            //dynamic priorityfee
-            let mut priority_fee;
-
-            if best_difficulty < 17 {
-                priority_fee = constants::LOW_PRIORITY_FEE;
-            } else if best_difficulty < 20 {
-                priority_fee = constants::MEDIUM_PRIORITY_FEE;
-            } else if best_difficulty < 24 {
-                priority_fee = constants::HIGH_PRIORITY_FEE;
+            // Priority fee: either the static difficulty ladder, or (if requested) a fee
+            // derived from the cluster's recently observed prioritization fees.
+            let priority_fee = if args.dynamic_fee {
+                let accounts = [bus, proof_pubkey(signer.pubkey()), ore_api::consts::CONFIG_ADDRESS];
+                match self
+                    .get_dynamic_priority_fee(&accounts, args.fee_percentile, best_difficulty)
+                    .await
+                {
+                    Some(fee) => fee.min(args.max_priority_fee),
+                    None => static_priority_fee(best_difficulty),
+                }
             } else {
-                priority_fee = constants::ULTRA_PRIORITY_FEE;
-            }
+                static_priority_fee(best_difficulty)
+            };
 
             println!("pri fee {}", priority_fee);
 
@@ -100,23 +133,43 @@ impl Miner {
         proof: Proof,
         cutoff_time: u64,
         cores: u64,
-        min_difficulty: u32,
-    ) -> (Solution, u32) {
+        target_difficulty: Difficulty,
+        risk_time: u64,
+        nonce_checkpoint_step: u64,
+    ) -> (Solution, Difficulty) {
+        // Guard against a zero step from the CLI, which would divide by zero on every
+        // iteration of the hot loop below.
+        let nonce_checkpoint_step = nonce_checkpoint_step.max(1);
+
         // Dispatch job to each thread
         let progress_bar = Arc::new(spinner::new_progress_bar());
         progress_bar.set_message("Mining...");
         let core_ids = core_affinity::get_core_ids().unwrap();
+        // Nonces hashed per core, used to derive hashrate without disturbing the hot loop.
+        let hash_counts: Arc<Vec<AtomicU64>> =
+            Arc::new(core_ids.iter().map(|_| AtomicU64::new(0)).collect());
+        // Rolling window of aggregate H/s samples so transient dips don't dominate.
+        let hashrate_window: Arc<Mutex<VecDeque<f64>>> =
+            Arc::new(Mutex::new(VecDeque::with_capacity(HASHRATE_WINDOW)));
+        let mining_timer = Instant::now();
         let handles: Vec<_> = core_ids
             .into_iter()
             .map(|i| {
                 std::thread::spawn({
                     let proof = proof.clone();
                     let progress_bar = progress_bar.clone();
+                    let hash_counts = hash_counts.clone();
+                    let hashrate_window = hashrate_window.clone();
+                    // Under high core counts this per-thread allocation and equix's internal
+                    // buffers contend on the system allocator; build with `--features jemalloc`
+                    // to serve them from jemalloc's scalable arena allocator instead (wired up
+                    // as the crate's #[global_allocator] in lib.rs). See benches/hashrate.rs to
+                    // compare throughput with the feature on and off.
                     let mut memory = equix::SolverMemory::new();
                     move || {
                         // Return if core should not be used
                         if (i.id as u64).ge(&cores) {
-                            return (0, 0, Hash::default());
+                            return (0, Difficulty::default(), Hash::default());
                         }
 
                         // Pin to core
@@ -126,8 +179,11 @@ impl Miner {
                         let timer = Instant::now();
                         let mut nonce = u64::MAX.saturating_div(cores).saturating_mul(i.id as u64);
                         let mut best_nonce = nonce;
-                        let mut best_difficulty = 0;
+                        let mut best_difficulty = Difficulty::default();
                         let mut best_hash = Hash::default();
+                        // Previous (per-core counts, elapsed secs) sample, so the spinner can
+                        // report an instantaneous rate instead of a cumulative one.
+                        let mut prev_sample: Option<(Vec<u64>, f64)> = None;
                         loop {
                             // Create hash
                             if let Ok(hx) = drillx::hash_with_memory(
@@ -135,26 +191,102 @@ impl Miner {
                                 &proof.challenge,
                                 &nonce.to_le_bytes(),
                             ) {
-                                let difficulty = hx.difficulty();
+                                let difficulty = Difficulty::from(hx.difficulty());
                                 if difficulty.gt(&best_difficulty) {
                                     best_nonce = nonce;
                                     best_difficulty = difficulty;
                                     best_hash = hx;
                                 }
                             }
+                            hash_counts[i.id as usize].fetch_add(1, Ordering::Relaxed);
 
                             // Exit if time has elapsed
-                            if nonce % 100 == 0 {
-                                if timer.elapsed().as_secs().ge(&cutoff_time) {
-                                    if best_difficulty.ge(&min_difficulty) {
-                                        // Mine until min difficulty has been met
-                                        break;
+                            if nonce % nonce_checkpoint_step == 0 {
+                                let elapsed = timer.elapsed().as_secs();
+
+                                // Sample hashrate and refresh the spinner from core 0 only.
+                                if i.id == 0 {
+                                    let elapsed_secs = timer.elapsed().as_secs_f64();
+                                    let rate_suffix = if elapsed_secs > 0.0 {
+                                        let counts: Vec<u64> = hash_counts
+                                            .iter()
+                                            .take(cores as usize)
+                                            .map(|c| c.load(Ordering::Relaxed))
+                                            .collect();
+
+                                        // Instantaneous per-core rate since the last checkpoint,
+                                        // not the cumulative rate since the round started, so a
+                                        // real slowdown (thermal throttling) shows up immediately
+                                        // instead of being diluted across the whole round.
+                                        let per_core_rates: Vec<f64> = match &prev_sample {
+                                            Some((prev_counts, prev_secs)) => {
+                                                let delta_secs = elapsed_secs - prev_secs;
+                                                counts
+                                                    .iter()
+                                                    .zip(prev_counts.iter())
+                                                    .map(|(count, prev_count)| {
+                                                        if delta_secs > 0.0 {
+                                                            count.saturating_sub(*prev_count) as f64
+                                                                / delta_secs
+                                                        } else {
+                                                            0.0
+                                                        }
+                                                    })
+                                                    .collect()
+                                            }
+                                            None => counts
+                                                .iter()
+                                                .map(|count| *count as f64 / elapsed_secs)
+                                                .collect(),
+                                        };
+                                        prev_sample = Some((counts, elapsed_secs));
+
+                                        let aggregate_rate: f64 = per_core_rates.iter().sum();
+                                        let mut window = hashrate_window.lock().unwrap();
+                                        if window.len() == HASHRATE_WINDOW {
+                                            window.pop_front();
+                                        }
+                                        window.push_back(aggregate_rate);
+                                        let avg_rate =
+                                            window.iter().sum::<f64>() / window.len() as f64;
+                                        let per_core = per_core_rates
+                                            .iter()
+                                            .enumerate()
+                                            .map(|(id, rate)| format!("core {}: {:.0} H/s", id, rate))
+                                            .collect::<Vec<_>>()
+                                            .join(", ");
+                                        format!(", {:.0} H/s avg ({})", avg_rate, per_core)
+                                    } else {
+                                        String::new()
+                                    };
+
+                                    if elapsed.lt(&cutoff_time) {
+                                        progress_bar.set_message(format!(
+                                            "Mining... ({} sec remaining{})",
+                                            cutoff_time.saturating_sub(elapsed),
+                                            rate_suffix,
+                                        ));
+                                    } else if best_difficulty.lt(&target_difficulty)
+                                        && elapsed.lt(&cutoff_time.saturating_add(risk_time))
+                                    {
+                                        progress_bar.set_message(format!(
+                                            "Mining... (risk overtime, {} sec left, {} to target{})",
+                                            cutoff_time
+                                                .saturating_add(risk_time)
+                                                .saturating_sub(elapsed),
+                                            best_difficulty.remaining(target_difficulty),
+                                            rate_suffix,
+                                        ));
                                     }
-                                } else if i.id == 0 {
-                                    progress_bar.set_message(format!(
-                                        "Mining... ({} sec remaining)",
-                                        cutoff_time.saturating_sub(timer.elapsed().as_secs()),
-                                    ));
+                                }
+
+                                // Once the deadline passes, keep hashing into risk overtime for a
+                                // shot at target_difficulty, but never past cutoff + risk_time.
+                                if elapsed.ge(&cutoff_time)
+                                    && (best_difficulty.ge(&target_difficulty)
+                                        || elapsed.ge(&cutoff_time.saturating_add(risk_time)))
+                                {
+                                    break;
                                 }
                             }
 
@@ -171,7 +303,7 @@ impl Miner {
 
         // Join handles and return best nonce
         let mut best_nonce = 0;
-        let mut best_difficulty = 0;
+        let mut best_difficulty = Difficulty::default();
         let mut best_hash = Hash::default();
         for h in handles {
             if let Ok((nonce, difficulty, hash)) = h.join() {
@@ -183,6 +315,29 @@ impl Miner {
             }
         }
 
+        // Final hashrate summary: aggregate and per-core, excluding cores that
+        // returned early because they weren't enabled via `cores`.
+        let total_elapsed = mining_timer.elapsed().as_secs_f64();
+        if total_elapsed > 0.0 {
+            let per_core: Vec<u64> = hash_counts
+                .iter()
+                .take(cores as usize)
+                .map(|c| c.load(Ordering::Relaxed))
+                .collect();
+            let aggregate: u64 = per_core.iter().sum();
+            let per_core_rates = per_core
+                .iter()
+                .enumerate()
+                .map(|(id, count)| format!("core {}: {:.0} H/s", id, *count as f64 / total_elapsed))
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "Hashrate: {:.0} H/s aggregate ({})",
+                aggregate as f64 / total_elapsed,
+                per_core_rates
+            );
+        }
+
         // Update log
         progress_bar.finish_with_message(format!(
             "Best hash: {} (difficulty: {})",
@@ -223,6 +378,50 @@ impl Miner {
             .saturating_sub(clock.unix_timestamp)
             .max(0) as u64
     }
+
+    /// Derives a priority fee from the cluster's recently observed prioritization fees over
+    /// `accounts`, scaled by `difficulty` so higher-value submissions bid more aggressively.
+    /// Returns `None` if the RPC has no samples, so the caller can fall back to the static
+    /// ladder (`max_priority_fee` capping is left to the caller as well).
+    async fn get_dynamic_priority_fee(
+        &self,
+        accounts: &[Pubkey],
+        percentile: u8,
+        difficulty: Difficulty,
+    ) -> Option<u64> {
+        let samples = self
+            .rpc_client
+            .get_recent_prioritization_fees(accounts)
+            .await
+            .ok()?;
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut fees: Vec<u64> = samples.iter().map(|s| s.prioritization_fee).collect();
+        fees.sort_unstable();
+        let index = (percentile as usize * fees.len() / 100).min(fees.len() - 1);
+        let base_fee = fees[index];
+
+        // Scale the observed fee linearly with difficulty (1 + difficulty / 8), so a
+        // 16-difficulty hash bids 3x the base fee and a 24-difficulty hash 4x.
+        let multiplier = 1.0 + (difficulty.get() as f64 / 8.0);
+        Some((base_fee as f64 * multiplier).round() as u64)
+    }
+}
+
+/// The static difficulty ladder, used when dynamic fees are disabled or the RPC has no
+/// recent prioritization fee samples to derive one from.
+fn static_priority_fee(difficulty: Difficulty) -> u64 {
+    if difficulty < Difficulty::from(17) {
+        constants::LOW_PRIORITY_FEE
+    } else if difficulty < Difficulty::from(20) {
+        constants::MEDIUM_PRIORITY_FEE
+    } else if difficulty < Difficulty::from(24) {
+        constants::HIGH_PRIORITY_FEE
+    } else {
+        constants::ULTRA_PRIORITY_FEE
+    }
 }
 
 // TODO Pick a better strategy (avoid draining bus)